@@ -3,7 +3,10 @@
 use std::env;
 use std::fs::File;
 use std::io::{self, prelude::*};
+use std::panic;
 use std::process;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
 
 // Solve sudokus using backtracking.
 
@@ -11,22 +14,61 @@ use std::process;
 // most cases. It's ported from:
 //   https://github.com/selliott512/julia-sudoku-solvers
 
-// Get an array of booleans indicating the fixed values in a sudoku.
-fn sud_get_fixed(sud: &[[i8; 9]; 9]) -> [[bool; 9]; 9] {
-    let mut z = [[false; 9]; 9];
-    for r in 0..9 {
-        for c in 0..9 {
-            z[r][c] = sud[r][c] > 0;
-        }
+// A sudoku board made up of box_size² x box_size² cells, grouped into
+// box_size x box_size boxes. This covers the classic 9x9 (box_size 3) as
+// well as 4x4, 16x16, 25x25, etc.
+#[derive(Clone)]
+struct Sud {
+    box_size: usize,
+    cells: Vec<Vec<i8>>,
+}
+
+impl Sud {
+    // The length of a side, i.e. box_size².
+    fn side(&self) -> usize {
+        self.box_size * self.box_size
+    }
+}
+
+// Derive the box dimension from a side length, panicking if side is not a
+// perfect square.
+fn sud_box_size(side: usize) -> usize {
+    let box_size = (side as f64).sqrt().round() as usize;
+    if box_size * box_size != side {
+        panic!("Side length {} is not a perfect square", side);
+    }
+    box_size
+}
+
+// Convert a glyph read from a file into a cell value. '.' is empty, 1-9 are
+// themselves, and A-Z (or a-z) extend the range for sides above 9.
+fn sud_glyph_to_val(glyph: char) -> i8 {
+    match glyph {
+        '.' => 0,
+        '1'..='9' => glyph as i8 - '0' as i8,
+        'A'..='Z' => glyph as i8 - 'A' as i8 + 10,
+        'a'..='z' => glyph as i8 - 'a' as i8 + 10,
+        _ => panic!("Invalid sudoku glyph: '{}'", glyph),
+    }
+}
+
+// Convert a cell value back into the glyph used to print it.
+fn sud_val_to_glyph(val: i8) -> char {
+    if val == 0 {
+        '.'
+    } else if val <= 9 {
+        (b'0' + val as u8) as char
+    } else {
+        (b'A' + (val - 10) as u8) as char
     }
-    return z;
 }
 
 // Return true if a sudoku is solved (no 0s).
-fn sud_is_solved(sud: &[[i8; 9]; 9]) -> bool {
-    for r in 0..9 {
-        for c in 0..9 {
-            if sud[r][c] == 0 {
+fn sud_is_solved(sud: &Sud) -> bool {
+    let side = sud.side();
+    for r in 0..side {
+        for c in 0..side {
+            if sud.cells[r][c] == 0 {
                 return false;
             }
         }
@@ -35,10 +77,11 @@ fn sud_is_solved(sud: &[[i8; 9]; 9]) -> bool {
 }
 
 // Check the entire thing.
-fn sud_is_valid(sud: &[[i8; 9]; 9]) -> bool {
-    for r in 0..9 {
-        for c in 0..9 {
-            if sud[r][c] > 0 && !sud_cell_is_valid(sud, r, c) {
+fn sud_is_valid(sud: &Sud) -> bool {
+    let side = sud.side();
+    for r in 0..side {
+        for c in 0..side {
+            if sud.cells[r][c] > 0 && !sud_cell_is_valid(sud, r, c) {
                 return false;
             }
         }
@@ -47,39 +90,40 @@ fn sud_is_valid(sud: &[[i8; 9]; 9]) -> bool {
 }
 
 // Check a particular cell
-fn sud_cell_is_valid(sud: &[[i8; 9]; 9], row: usize, col: usize) -> bool {
-    let val = sud[row][col];
+fn sud_cell_is_valid(sud: &Sud, row: usize, col: usize) -> bool {
+    let side = sud.side();
+    let val = sud.cells[row][col];
 
     // Check if conflicting in current row. This is done first to search in
     // row major order.
-    for c in 0..9 {
+    for c in 0..side {
         if c == col {
             continue;
         }
-        if sud[row][c] == val {
+        if sud.cells[row][c] == val {
             return false;
         }
     }
 
     // Check if conflicting in current col.
-    for r in 0..9 {
+    for r in 0..side {
         if r == row {
             continue;
         }
-        if sud[r][col] == val {
+        if sud.cells[r][col] == val {
             return false;
         }
     }
 
     // Check if conflicting in current box.
-    let row_start = 3 * (row / 3);
-    let col_start = 3 * (col / 3);
-    for r in row_start..row_start + 3 {
-        for c in col_start..col_start + 3 {
+    let row_start = sud.box_size * (row / sud.box_size);
+    let col_start = sud.box_size * (col / sud.box_size);
+    for r in row_start..row_start + sud.box_size {
+        for c in col_start..col_start + sud.box_size {
             if r == row && c == col {
                 continue;
             }
-            if sud[r][c] == val {
+            if sud.cells[r][c] == val {
                 return false;
             }
         }
@@ -89,171 +133,611 @@ fn sud_cell_is_valid(sud: &[[i8; 9]; 9], row: usize, col: usize) -> bool {
     return true;
 }
 
-// Print a sudoku to stdout.
-fn sud_print(sud: &[[i8; 9]; 9]) {
-    for row in 0..9 {
-        let mut row_str = String::with_capacity(9);
-        for col in 0..9 {
-            row_str.push_str(&sud[row][col].to_string());
+// Index of the box that a cell belongs to, 0..side in row major order.
+fn sud_box(sud: &Sud, row: usize, col: usize) -> usize {
+    sud.box_size * (row / sud.box_size) + col / sud.box_size
+}
+
+// Build the row, column and box bitmasks for a sudoku. Bit `v - 1` of
+// row_mask[r] (and similarly for col_mask/box_mask) is set when digit v is
+// already present in that unit.
+fn sud_build_masks(sud: &Sud) -> (Vec<u32>, Vec<u32>, Vec<u32>) {
+    let side = sud.side();
+    let mut row_mask = vec![0_u32; side];
+    let mut col_mask = vec![0_u32; side];
+    let mut box_mask = vec![0_u32; side];
+    for r in 0..side {
+        for c in 0..side {
+            if sud.cells[r][c] > 0 {
+                let bit = 1_u32 << (sud.cells[r][c] - 1);
+                row_mask[r] |= bit;
+                col_mask[c] |= bit;
+                box_mask[sud_box(sud, r, c)] |= bit;
+            }
         }
-        row_str = row_str.replace("0", ".");
-        println!(
-            "{} {} {}",
-            row_str.get(0..3).unwrap(),
-            &row_str.get(3..6).unwrap(),
-            row_str.get(6..9).unwrap()
-        );
-        if row == 2 || row == 5 {
-            println!();
+    }
+    (row_mask, col_mask, box_mask)
+}
+
+// Render a sudoku to the text format written to stdout: glyphs grouped into
+// box_size-wide blocks, with a blank line every box_size rows.
+fn sud_render(sud: &Sud) -> String {
+    let side = sud.side();
+    let mut out = String::new();
+    for row in 0..side {
+        let mut row_str = String::with_capacity(side);
+        for col in 0..side {
+            row_str.push(sud_val_to_glyph(sud.cells[row][col]));
+        }
+        let blocks: Vec<&str> = (0..side)
+            .step_by(sud.box_size)
+            .map(|start| row_str.get(start..start + sud.box_size).unwrap())
+            .collect();
+        out.push_str(&blocks.join(" "));
+        out.push('\n');
+        if (row + 1) % sud.box_size == 0 && row + 1 != side {
+            out.push('\n');
         }
     }
+    out
 }
 
-// Read a sudoku from a file.
-fn sud_read(path: &str) -> [[i8; 9]; 9] {
-    let mut sud = [[0_i8; 9]; 9];
-    let mut line_num = 0;
-    let mut sud_row = 0;
-    let path_hand = match File::open(path) {
-        Ok(fhand) => fhand,
-        Err(error) => panic!("Unable to open {} for read: {:?}", path, error),
+// Read a sudoku from a file, or from stdin if path is "-". Two formats are
+// understood: the usual grid of glyphs, where the side (and therefore
+// box_size) is auto-detected from the length of the first non-comment,
+// non-blank line, and a coordinate-list format for sparse/generated
+// puzzles, selected when that first line instead contains a comma: a
+// "rows,cols" header (e.g. "9,9") followed by 0-based "row,col,value"
+// triples (1-based value, 0 meaning empty).
+fn sud_read(path: &str) -> Sud {
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(io::BufReader::new(io::stdin()))
+    } else {
+        let path_hand = match File::open(path) {
+            Ok(fhand) => fhand,
+            Err(error) => panic!("Unable to open {} for read: {:?}", path, error),
+        };
+        Box::new(io::BufReader::new(path_hand))
     };
-    let lines = io::BufReader::new(path_hand).lines();
-    for line in lines {
+
+    let mut cells: Vec<Vec<i8>> = vec![];
+    let mut side = 0;
+    let mut line_num = 0;
+    let mut coords = false;
+    for line in reader.lines() {
         line_num += 1;
         let line_uw = line.unwrap();
         let trim_line = line_uw.trim().replace(" ", "");
         if trim_line == "" || trim_line.starts_with("#") {
             continue;
         }
-        if trim_line.len() != 9 {
-            panic!(
-                "Line #{} of \"{}\" does not have 9 digts: {}",
-                line_num, path, line_uw
-            );
+        if side == 0 {
+            if trim_line.contains(',') {
+                coords = true;
+                let dims: Vec<&str> = trim_line.split(',').collect();
+                if dims.len() != 2 || dims[0] != dims[1] {
+                    panic!(
+                        "Line #{} of \"{}\" is not a valid square \"rows,cols\" header: {}",
+                        line_num, path, line_uw
+                    );
+                }
+                side = dims[0].parse().unwrap();
+                sud_box_size(side); // Validate that side is a perfect square.
+                cells = vec![vec![0_i8; side]; side];
+                continue;
+            }
+            side = trim_line.len();
+            sud_box_size(side); // Validate that side is a perfect square.
         }
-        let trim_line = &trim_line.replace(".", "0");
-        for (i, c) in trim_line.chars().enumerate() {
-            sud[sud_row][i] = c as i8 - '0' as i8;
+
+        if coords {
+            let fields: Vec<&str> = trim_line.split(',').collect();
+            if fields.len() != 3 {
+                panic!(
+                    "Line #{} of \"{}\" is not a valid \"row,col,value\" triple: {}",
+                    line_num, path, line_uw
+                );
+            }
+            let row: usize = fields[0].parse().unwrap();
+            let col: usize = fields[1].parse().unwrap();
+            let val: i8 = fields[2].parse().unwrap();
+            if row >= side || col >= side || val < 0 || val as usize > side {
+                panic!(
+                    "Line #{} of \"{}\" has an out-of-range \"row,col,value\" triple: {}",
+                    line_num, path, line_uw
+                );
+            }
+            cells[row][col] = val;
+        } else {
+            if trim_line.len() != side {
+                panic!(
+                    "Line #{} of \"{}\" does not have {} glyphs: {}",
+                    line_num, path, side, line_uw
+                );
+            }
+            let row: Vec<i8> = trim_line.chars().map(sud_glyph_to_val).collect();
+            for &val in &row {
+                if val as usize > side {
+                    panic!(
+                        "Line #{} of \"{}\" has a glyph greater than the puzzle side {}: {}",
+                        line_num, path, side, line_uw
+                    );
+                }
+            }
+            cells.push(row);
         }
-        sud_row += 1;
     }
-    return sud;
+    Sud {
+        box_size: sud_box_size(side),
+        cells,
+    }
 }
 
-// Solve a sudoku write the solution to stdout.
-fn sud_solve(sud: &[[i8; 9]; 9]) {
-    // The original version is needed for error messages.
-    let mut sud_cp = sud.clone();
+// The 3 * side units (side rows, side cols, side boxes) as lists of their
+// member cells, used by the hidden single rule below.
+fn sud_units(sud: &Sud) -> Vec<Vec<(usize, usize)>> {
+    let side = sud.side();
+    let k = sud.box_size;
+    let mut units = Vec::with_capacity(3 * side);
+    for i in 0..side {
+        units.push((0..side).map(|j| (i, j)).collect()); // Row i.
+    }
+    for i in 0..side {
+        units.push((0..side).map(|j| (j, i)).collect()); // Col i.
+    }
+    for i in 0..side {
+        units.push(
+            (0..side)
+                .map(|j| (k * (i / k) + j / k, k * (i % k) + j % k))
+                .collect(),
+        ); // Box i.
+    }
+    units
+}
 
-    let fixed = sud_get_fixed(&sud_cp);
+// Apply naked single and hidden single deduction to a fixpoint, mutating
+// sud, row_mask, col_mask and box_mask in place as cells are assigned.
+// Returns false if a contradiction is found (a cell with no candidates
+// left), true otherwise (the sudoku may or may not be fully solved).
+fn sud_propagate(
+    sud: &mut Sud,
+    row_mask: &mut [u32],
+    col_mask: &mut [u32],
+    box_mask: &mut [u32],
+) -> bool {
+    let side = sud.side();
+    let box_size = sud.box_size;
+    let full_mask = (1_u32 << side) - 1;
+    let units = sud_units(sud);
+    loop {
+        let mut changed = false;
 
-    // Step to first non-fixed cell. In row major order this is the first
-    // non-fixed cell after [0, -1].
-    let (mut row, mut col) = sud_step(&fixed, 0, -1_isize as usize, 1);
+        // Naked single: a cell with exactly one candidate must be it.
+        for (r, row_cells) in sud.cells.iter_mut().enumerate() {
+            for (c, cell) in row_cells.iter_mut().enumerate() {
+                if *cell != 0 {
+                    continue;
+                }
+                let b = box_size * (r / box_size) + c / box_size;
+                let cand = !(row_mask[r] | col_mask[c] | box_mask[b]) & full_mask;
+                if cand == 0 {
+                    return false;
+                }
+                if cand.count_ones() == 1 {
+                    *cell = cand.trailing_zeros() as i8 + 1;
+                    row_mask[r] |= cand;
+                    col_mask[c] |= cand;
+                    box_mask[b] |= cand;
+                    changed = true;
+                }
+            }
+        }
 
-    // Set row to 9 to it breaks out of the loop for invalid sudokus.
-    if !sud_is_valid(&sud_cp) {
-        row = 9;
-    }
-
-    // If the above stepped past the end then it is a solved sudoku, and we
-    // just need to check it.
-    let mut found = row == 9;
-    while row != 9 {
-        let mut val = sud_cp[row][col];
-        val += 1;
-        if val > 9 {
-            sud_cp[row][col] = 0;
-            // Step one backward.
-            let (r, c) = sud_step(&fixed, row, col, -1);
-            row = r;
-            col = c;
-            continue;
+        // Hidden single: a digit that has exactly one possible cell left in
+        // a unit must go there.
+        for unit in &units {
+            for v in 1..=side as u32 {
+                let bit = 1_u32 << (v - 1);
+                let mut count = 0;
+                let mut last = (0, 0);
+                for &(r, c) in unit {
+                    if sud.cells[r][c] != 0 {
+                        continue;
+                    }
+                    let cand =
+                        !(row_mask[r] | col_mask[c] | box_mask[sud_box(sud, r, c)]) & full_mask;
+                    if cand & bit != 0 {
+                        count += 1;
+                        last = (r, c);
+                    }
+                }
+                if count == 1 {
+                    let (r, c) = last;
+                    sud.cells[r][c] = v as i8;
+                    row_mask[r] |= bit;
+                    col_mask[c] |= bit;
+                    box_mask[sud_box(sud, r, c)] |= bit;
+                    changed = true;
+                }
+            }
         }
-        sud_cp[row][col] = val;
-        if sud_cell_is_valid(&sud_cp, row, col) {
-            // Step one forward
-            let (r, c) = sud_step(&fixed, row, col, 1);
-            row = r;
-            col = c;
-            if row == 9 {
-                // Went past the end - must be solved.
-                found = true;
+
+        if !changed {
+            return true;
+        }
+    }
+}
+
+// Select the empty cell with the fewest remaining candidates (Minimum
+// Remaining Values). Returns the cell's row, column and candidate mask, or
+// None if the sudoku is already full. A cell with a candidate mask of 0
+// means that cell (and therefore the whole branch) is a dead end.
+fn sud_select_cell(
+    sud: &Sud,
+    row_mask: &[u32],
+    col_mask: &[u32],
+    box_mask: &[u32],
+) -> Option<(usize, usize, u32)> {
+    let side = sud.side();
+    let full_mask = (1_u32 << side) - 1;
+    let mut best: Option<(usize, usize, u32)> = None;
+    for r in 0..side {
+        for c in 0..side {
+            if sud.cells[r][c] != 0 {
+                continue;
+            }
+            let cand = !(row_mask[r] | col_mask[c] | box_mask[sud_box(sud, r, c)]) & full_mask;
+            if cand == 0 {
+                // Dead end - return immediately, no point searching further.
+                return Some((r, c, 0));
+            }
+            let better = match best {
+                None => true,
+                Some((_, _, best_cand)) => cand.count_ones() < best_cand.count_ones(),
+            };
+            if better {
+                best = Some((r, c, cand));
             }
         }
     }
+    best
+}
 
-    let mut errors = vec![];
-    if found {
-        if !sud_is_valid(&sud_cp) {
-            errors.push("not valid");
+// Recursively backtrack using MRV cell selection and the row/col/box
+// bitmasks, mutating sud, row_mask, col_mask and box_mask in place. Returns
+// true if a solution was found, in which case sud holds it.
+fn sud_backtrack(
+    sud: &mut Sud,
+    row_mask: &mut [u32],
+    col_mask: &mut [u32],
+    box_mask: &mut [u32],
+) -> bool {
+    let (row, col, mut cand) = match sud_select_cell(sud, row_mask, col_mask, box_mask) {
+        None => return true, // No empty cells left - solved.
+        Some(cell) => cell,
+    };
+    if cand == 0 {
+        return false;
+    }
+
+    let b = sud_box(sud, row, col);
+    while cand != 0 {
+        let bit = cand & cand.wrapping_neg(); // Lowest set bit.
+        cand &= cand - 1; // Clear it for the next iteration.
+
+        sud.cells[row][col] = bit.trailing_zeros() as i8 + 1;
+        row_mask[row] |= bit;
+        col_mask[col] |= bit;
+        box_mask[b] |= bit;
+
+        if sud_backtrack(sud, row_mask, col_mask, box_mask) {
+            return true;
         }
-        if !sud_is_solved(&sud_cp) {
-            errors.push("not solved");
+
+        sud.cells[row][col] = 0;
+        row_mask[row] &= !bit;
+        col_mask[col] &= !bit;
+        box_mask[b] &= !bit;
+    }
+
+    false
+}
+
+// Stop counting solutions once this many have been found - we only need to
+// distinguish zero, one and "more than one".
+const SUD_COUNT_CAP: usize = 2;
+
+// Like sud_backtrack, but keeps searching past the first solution found,
+// incrementing count for each one, until count reaches cap.
+fn sud_backtrack_count(
+    sud: &mut Sud,
+    row_mask: &mut [u32],
+    col_mask: &mut [u32],
+    box_mask: &mut [u32],
+    count: &mut usize,
+    cap: usize,
+) {
+    let (row, col, mut cand) = match sud_select_cell(sud, row_mask, col_mask, box_mask) {
+        None => {
+            *count += 1; // No empty cells left - a solution.
+            return;
         }
-        if errors.len() > 0 {
+        Some(cell) => cell,
+    };
+    if cand == 0 {
+        return;
+    }
+
+    let b = sud_box(sud, row, col);
+    while cand != 0 && *count < cap {
+        let bit = cand & cand.wrapping_neg(); // Lowest set bit.
+        cand &= cand - 1; // Clear it for the next iteration.
+
+        sud.cells[row][col] = bit.trailing_zeros() as i8 + 1;
+        row_mask[row] |= bit;
+        col_mask[col] |= bit;
+        box_mask[b] |= bit;
+
+        sud_backtrack_count(sud, row_mask, col_mask, box_mask, count, cap);
+
+        sud.cells[row][col] = 0;
+        row_mask[row] &= !bit;
+        col_mask[col] &= !bit;
+        box_mask[b] &= !bit;
+    }
+}
+
+// Count the number of solutions a sudoku has, up to cap.
+fn sud_count_solutions(sud: &Sud, cap: usize) -> usize {
+    let mut sud_cp = sud.clone();
+    if !sud_is_valid(&sud_cp) {
+        return 0;
+    }
+
+    let (mut row_mask, mut col_mask, mut box_mask) = sud_build_masks(&sud_cp);
+    if !sud_propagate(&mut sud_cp, &mut row_mask, &mut col_mask, &mut box_mask) {
+        return 0;
+    }
+
+    let mut count = 0;
+    sud_backtrack_count(
+        &mut sud_cp,
+        &mut row_mask,
+        &mut col_mask,
+        &mut box_mask,
+        &mut count,
+        cap,
+    );
+    count
+}
+
+// Render whether a sudoku has zero, exactly one, or multiple solutions.
+fn sud_count_text(sud: &Sud) -> String {
+    match sud_count_solutions(sud, SUD_COUNT_CAP) {
+        0 => "No solution\n".to_string(),
+        1 => "Unique solution\n".to_string(),
+        _ => "Multiple solutions\n".to_string(),
+    }
+}
+
+// Report whether a sudoku has zero, exactly one, or multiple solutions.
+fn sud_solve_count(sud: &Sud) {
+    print!("{}", sud_count_text(sud));
+}
+
+// Why a sudoku could not be solved, carrying the board to show for
+// diagnostics (the invalid "solution" found, or the original puzzle).
+enum SolveError {
+    NoSolution(Sud),
+    Invalid(Sud, Vec<&'static str>),
+}
+
+// Solve a sudoku, returning the rendered solution, or an error describing
+// why it couldn't be solved. Does no I/O itself so that it can be called
+// from worker threads.
+fn sud_solve_render(sud: &Sud) -> Result<String, SolveError> {
+    // The original version is needed for error messages.
+    let mut sud_cp = sud.clone();
+
+    let found = if sud_is_valid(&sud_cp) {
+        let (mut row_mask, mut col_mask, mut box_mask) = sud_build_masks(&sud_cp);
+        // Exhaust pure deduction before falling back to backtracking -
+        // many puzzles solve completely in this phase.
+        if sud_propagate(&mut sud_cp, &mut row_mask, &mut col_mask, &mut box_mask) {
+            sud_backtrack(&mut sud_cp, &mut row_mask, &mut col_mask, &mut box_mask)
+        } else {
+            false
+        }
+    } else {
+        false
+    };
+
+    if !found {
+        return Err(SolveError::NoSolution(sud.clone()));
+    }
+
+    let mut errors = vec![];
+    if !sud_is_valid(&sud_cp) {
+        errors.push("not valid");
+    }
+    if !sud_is_solved(&sud_cp) {
+        errors.push("not solved");
+    }
+    if !errors.is_empty() {
+        return Err(SolveError::Invalid(sud_cp, errors));
+    }
+
+    Ok(sud_render(&sud_cp))
+}
+
+// Render the outcome of solving one puzzle, along with whether it failed.
+// Diagnostics for a failure are written to stderr immediately (their
+// relative order across puzzles isn't significant), while the returned
+// text is the stdout output, which callers are responsible for emitting in
+// input order.
+fn sud_solve_text(sud: &Sud) -> (String, bool) {
+    match sud_solve_render(sud) {
+        Ok(rendered) => (rendered, false),
+        Err(SolveError::Invalid(sud_cp, errors)) => {
             eprintln!("Found an invalid solution ({}):", errors.join(", "));
+            (sud_render(&sud_cp), true)
         }
-        sud_print(&sud_cp);
-        if errors.len() > 0 {
-            process::exit(1);
+        Err(SolveError::NoSolution(orig)) => {
+            eprintln!("Could not find a solution for:");
+            (sud_render(&orig), true)
         }
-    } else {
-        eprintln!("Could not find a solution for:");
-        sud_print(sud); // The original sud.
+    }
+}
+
+// Solve a sudoku write the solution to stdout.
+fn sud_solve(sud: &Sud) {
+    let (rendered, failed) = sud_solve_text(sud);
+    print!("{}", rendered);
+    if failed {
         process::exit(1);
     }
 }
 
-// Solve multiple sodoku puzzles given their paths.
-fn sud_solves(paths: &[String]) {
+// Solve multiple sudoku puzzles given their paths sequentially, printing
+// puzzles in input order as each one finishes, with a blank line between
+// puzzles. If count is true, report solution uniqueness instead of
+// printing the first solution.
+fn sud_solves_sequential(paths: &[String], count: bool) {
     let mut first = true;
     let mut last_path = "";
-    let mut sud = [[0_i8; 9]; 9];
+    let mut sud: Option<Sud> = None;
     for path in paths {
         if first {
             first = false;
         } else {
             println!();
         }
-        // If the path has not been changed then sud can be reused.
-        if path != last_path {
-            sud = sud_read(path);
+        // If the path has not been changed then sud can be reused. "-"
+        // (stdin) is never reusable since it can't be read twice.
+        if path == "-" || path != last_path || sud.is_none() {
+            sud = Some(sud_read(path));
+        }
+        if count {
+            sud_solve_count(sud.as_ref().unwrap());
+        } else {
+            sud_solve(sud.as_ref().unwrap());
         }
-        sud_solve(&sud);
         last_path = path;
     }
 }
 
-// Step forward or backward to the next non-fixed location. Return zeros if
-// such a location can not be found. The step is row major order.
-fn sud_step(fixed: &[[bool; 9]; 9], row: usize, col: usize, inc: isize) -> (usize, usize) {
-    let mut irow = row as isize;
-    let mut icol = col as isize;
-    loop {
-        icol += inc;
-        if icol < 0 {
-            icol = 8;
-            irow -= 1;
-        } else if icol > 8 {
-            icol = 0;
-            irow += 1;
-        }
-        if irow < 0 || irow > 8 {
-            // 9 indicating out of range
-            return (9, 9);
-        }
-        if !fixed[irow as usize][icol as usize] {
-            return (irow as usize, icol as usize);
+// Solve multiple sudoku puzzles given their paths, distributing them
+// across a pool of worker threads. Results are collected and printed in
+// input order once every puzzle is done, so throughput scales with cores
+// while output stays deterministic.
+fn sud_solves_parallel(paths: &[String], count: bool) {
+    let num_workers = thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(paths.len().max(1));
+
+    let (job_tx, job_rx) = mpsc::channel::<(usize, String)>();
+    let job_rx = Arc::new(Mutex::new(job_rx));
+    let (result_tx, result_rx) = mpsc::channel::<(usize, String, bool)>();
+
+    // A malformed puzzle file is routine, not a bug, so one worker
+    // panicking (sud_read panics on bad input) must not take down the
+    // whole batch or the main thread's recv() below. Silence the default
+    // panic hook - catch_unwind below reports a proper per-path message
+    // instead - and restore it once every worker has been joined.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut workers = Vec::with_capacity(num_workers);
+    for _ in 0..num_workers {
+        let job_rx = Arc::clone(&job_rx);
+        let result_tx = result_tx.clone();
+        workers.push(thread::spawn(move || loop {
+            let job = job_rx.lock().unwrap().recv();
+            let (index, path) = match job {
+                Ok(job) => job,
+                Err(_) => break,
+            };
+            let outcome = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                let sud = sud_read(&path);
+                if count {
+                    (sud_count_text(&sud), false)
+                } else {
+                    sud_solve_text(&sud)
+                }
+            }));
+            let (rendered, failed) = match outcome {
+                Ok(result) => result,
+                Err(payload) => {
+                    eprintln!("Could not solve \"{}\": {}", path, sud_panic_message(&payload));
+                    (String::new(), true)
+                }
+            };
+            if result_tx.send((index, rendered, failed)).is_err() {
+                break;
+            }
+        }));
+    }
+    drop(result_tx);
+
+    for (index, path) in paths.iter().enumerate() {
+        job_tx.send((index, path.clone())).unwrap();
+    }
+    drop(job_tx);
+
+    let mut results: Vec<Option<(String, bool)>> = vec![None; paths.len()];
+    for _ in 0..paths.len() {
+        let (index, rendered, failed) = result_rx.recv().unwrap();
+        results[index] = Some((rendered, failed));
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    panic::set_hook(default_hook);
+
+    let mut any_failed = false;
+    for (index, result) in results.into_iter().enumerate() {
+        if index > 0 {
+            println!();
         }
+        let (rendered, failed) = result.unwrap();
+        print!("{}", rendered);
+        any_failed |= failed;
+    }
+    if any_failed {
+        process::exit(1);
+    }
+}
+
+// Extract a human-readable message from a caught panic payload.
+fn sud_panic_message(payload: &Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown error".to_string()
+    }
+}
+
+// Solve multiple sudoku puzzles given their paths. If count is true, report
+// solution uniqueness instead of printing the first solution. If parallel
+// is true, puzzles are distributed across worker threads instead of being
+// solved one at a time.
+fn sud_solves(paths: &[String], count: bool, parallel: bool) {
+    if parallel {
+        sud_solves_parallel(paths, count);
+    } else {
+        sud_solves_sequential(paths, count);
     }
 }
 
 // Write a usage statement to stdout.
 fn usage() {
-    println!("rust-sudoku-solvers puzzle1.sud [puzzle2.sud ...]");
-    println!("  -h  This help message");
+    println!("rust-sudoku-solvers [-c] [-p] puzzle1.sud [puzzle2.sud ...]");
+    println!("  -c, --count     Report whether each puzzle has 0, 1 or multiple solutions");
+    println!("  -p, --parallel  Solve puzzles across a pool of worker threads");
+    println!("  -h              This help message");
 }
 
 // Main
@@ -265,5 +749,22 @@ fn main() {
         process::exit(0);
     }
 
-    sud_solves(&args[1..]);
+    let mut count = false;
+    let mut parallel = false;
+    let mut paths: Vec<String> = vec![];
+    for arg in &args[1..] {
+        if arg == "-c" || arg == "--count" {
+            count = true;
+        } else if arg == "-p" || arg == "--parallel" {
+            parallel = true;
+        } else {
+            paths.push(arg.clone());
+        }
+    }
+    if paths.is_empty() {
+        usage();
+        process::exit(0);
+    }
+
+    sud_solves(&paths, count, parallel);
 }